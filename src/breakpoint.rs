@@ -0,0 +1,113 @@
+//! Synthesizes debugger command-file snippets for automatic breakpoints,
+//! e.g. stopping at `main` or a named test function before running.
+
+/// How execution should resume after the breakpoint is set.
+pub enum Launch {
+    /// Start the process fresh (`run` / `process launch`).
+    Run,
+    /// Resume a process that's already stopped (`continue` / `process continue`),
+    /// e.g. after `--pid`/`--remote` attached it.
+    Continue,
+    /// Don't emit a resume command at all, e.g. against a `--core` dump.
+    None,
+}
+
+/// The startup commands needed to set a breakpoint on `symbol` and resume per `launch`.
+pub fn command_file_contents(debugger: &str, symbol: &str, launch: Launch) -> String {
+    let is_lldb = debugger.ends_with("lldb");
+
+    let resume = match (launch, is_lldb) {
+        (Launch::Run, false) => "run\n",
+        (Launch::Run, true) => "process launch\n",
+        (Launch::Continue, false) => "continue\n",
+        (Launch::Continue, true) => "process continue\n",
+        (Launch::None, _) => "",
+    };
+
+    if is_lldb {
+        format!("breakpoint set --name {}\n{}", symbol, resume)
+    } else {
+        format!("break {}\n{}", symbol, resume)
+    }
+}
+
+/// Resolve the command file to hand the debugger: if a breakpoint `symbol`
+/// was requested, synthesize a temporary command file containing the
+/// user's `command_file` (if any) followed by the breakpoint commands.
+/// Otherwise the user's `command_file` is used unchanged.
+pub fn resolve_command_file(
+    debugger: &str,
+    user_command_file: &Option<String>,
+    symbol: &Option<String>,
+    launch: Launch,
+) -> Option<String> {
+    let symbol = match symbol {
+        Some(s) => s,
+        None => return user_command_file.clone(),
+    };
+
+    let mut contents = String::new();
+    if let Some(path) = user_command_file {
+        let existing = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("failed to read command file {}: {}", path, e));
+        contents.push_str(&existing);
+        if !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+    }
+    contents.push_str(&command_file_contents(debugger, symbol, launch));
+
+    let path = std::env::temp_dir().join(format!("cargo-debug-break-{}.gdb", std::process::id()));
+    std::fs::write(&path, &contents).expect("failed to write breakpoint command file");
+
+    Some(path.to_str().unwrap().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gdb_run_breaks_and_runs() {
+        assert_eq!(command_file_contents("gdb", "main", Launch::Run), "break main\nrun\n");
+    }
+
+    #[test]
+    fn gdb_continue_does_not_restart() {
+        assert_eq!(command_file_contents("gdb", "main", Launch::Continue), "break main\ncontinue\n");
+    }
+
+    #[test]
+    fn gdb_none_emits_no_resume_command() {
+        assert_eq!(command_file_contents("gdb", "main", Launch::None), "break main\n");
+    }
+
+    #[test]
+    fn lldb_run_uses_process_launch() {
+        assert_eq!(
+            command_file_contents("lldb", "main", Launch::Run),
+            "breakpoint set --name main\nprocess launch\n"
+        );
+    }
+
+    #[test]
+    fn lldb_continue_uses_process_continue() {
+        assert_eq!(
+            command_file_contents("lldb", "main", Launch::Continue),
+            "breakpoint set --name main\nprocess continue\n"
+        );
+    }
+
+    #[test]
+    fn no_symbol_passes_user_file_through_unchanged() {
+        assert_eq!(
+            resolve_command_file("gdb", &Some("/tmp/foo.gdb".to_string()), &None, Launch::Run),
+            Some("/tmp/foo.gdb".to_string())
+        );
+    }
+
+    #[test]
+    fn no_symbol_and_no_user_file_is_none() {
+        assert_eq!(resolve_command_file("gdb", &None, &None, Launch::Run), None);
+    }
+}
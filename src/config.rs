@@ -0,0 +1,157 @@
+//! Project-level debugger defaults read from `[package.metadata.debug]` in
+//! `Cargo.toml`. CLI flags always take precedence over these defaults.
+
+use cargo_manifest::Manifest;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DebugMetadata {
+    pub debugger: Option<String>,
+    pub command_file: Option<String>,
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub commands: Vec<String>,
+}
+
+impl DebugMetadata {
+    /// Write `commands` out to a temporary command file for the debugger to
+    /// load, if any were given.
+    pub fn commands_file(&self) -> Option<String> {
+        if self.commands.is_empty() {
+            return None;
+        }
+
+        let mut contents = self.commands.join("\n");
+        contents.push('\n');
+
+        let path = std::env::temp_dir().join(format!("cargo-debug-manifest-{}.gdb", std::process::id()));
+        std::fs::write(&path, contents).expect("failed to write manifest command file");
+
+        Some(path.to_str().unwrap().to_string())
+    }
+
+    /// Resolve the debugger to use: a CLI flag wins, then this manifest's
+    /// `debugger`, then the built-in default.
+    pub fn resolve_debugger(&self, cli: &Option<String>) -> String {
+        cli.clone().or_else(|| self.debugger.clone()).unwrap_or_else(|| "gdb".to_string())
+    }
+
+    /// Resolve the command file to hand the debugger: a CLI `--command-file`
+    /// wins, then this manifest's `command-file`, then `commands` written
+    /// out to a temporary file (only consulted once the first two are both
+    /// absent).
+    pub fn resolve_command_file(&self, cli: &Option<String>) -> Option<String> {
+        cli.clone().or_else(|| self.command_file.clone()).or_else(|| self.commands_file())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PackageMetadata {
+    pub debug: Option<DebugMetadata>,
+}
+
+pub struct ManifestInfo {
+    pub package_name: String,
+    pub debug: DebugMetadata,
+}
+
+/// Load the package name and `[package.metadata.debug]` table from the
+/// manifest at `path`.
+pub fn load(path: &str) -> ManifestInfo {
+    let manifest: Manifest<PackageMetadata> =
+        Manifest::from_path_with_metadata(path).expect("Failed to read Cargo.toml");
+
+    let package = manifest.package.expect("No package available");
+
+    ManifestInfo {
+        package_name: package.name,
+        debug: package.metadata.and_then(|m| m.debug).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolve_debugger_cli_wins_over_manifest() {
+        let meta = DebugMetadata { debugger: Some("lldb".to_string()), ..Default::default() };
+        assert_eq!(meta.resolve_debugger(&Some("gdb".to_string())), "gdb");
+    }
+
+    #[test]
+    fn resolve_debugger_falls_back_to_manifest_then_default() {
+        let meta = DebugMetadata { debugger: Some("lldb".to_string()), ..Default::default() };
+        assert_eq!(meta.resolve_debugger(&None), "lldb");
+        assert_eq!(DebugMetadata::default().resolve_debugger(&None), "gdb");
+    }
+
+    #[test]
+    fn resolve_command_file_cli_wins_over_manifest() {
+        let meta = DebugMetadata { command_file: Some("manifest.gdb".to_string()), ..Default::default() };
+        assert_eq!(meta.resolve_command_file(&Some("cli.gdb".to_string())), Some("cli.gdb".to_string()));
+    }
+
+    #[test]
+    fn resolve_command_file_falls_back_to_manifest_command_file() {
+        let meta = DebugMetadata { command_file: Some("manifest.gdb".to_string()), ..Default::default() };
+        assert_eq!(meta.resolve_command_file(&None), Some("manifest.gdb".to_string()));
+    }
+
+    #[test]
+    fn commands_only_take_effect_when_command_file_is_absent() {
+        let meta = DebugMetadata {
+            command_file: Some("manifest.gdb".to_string()),
+            commands: vec!["break main".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(meta.resolve_command_file(&None), Some("manifest.gdb".to_string()));
+    }
+
+    #[test]
+    fn commands_are_used_when_nothing_else_is_set() {
+        let meta = DebugMetadata { commands: vec!["break main".to_string(), "run".to_string()], ..Default::default() };
+        let path = meta.resolve_command_file(&None).expect("commands should produce a command file");
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "break main\nrun\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_reads_package_name_and_debug_metadata() {
+        let path = std::env::temp_dir().join(format!("cargo-debug-test-manifest-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+[package]
+name = "demo"
+version = "0.1.0"
+
+[package.metadata.debug]
+debugger = "lldb"
+filter = "demo"
+"#,
+        )
+        .unwrap();
+
+        let info = load(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(info.package_name, "demo");
+        assert_eq!(info.debug.debugger, Some("lldb".to_string()));
+        assert_eq!(info.debug.filter, Some("demo".to_string()));
+    }
+
+    #[test]
+    fn load_defaults_debug_metadata_when_table_absent() {
+        let path = std::env::temp_dir().join(format!("cargo-debug-test-manifest-nodebug-{}.toml", std::process::id()));
+        std::fs::write(&path, "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n").unwrap();
+
+        let info = load(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(info.package_name, "demo");
+        assert_eq!(info.debug.debugger, None);
+    }
+}
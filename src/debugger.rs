@@ -0,0 +1,229 @@
+//! Pluggable debugger backends: each variant translates an abstract debug
+//! request into the argv for its own backend.
+
+use std::fs;
+
+/// Kinds of debugger this tool knows how to drive.
+pub enum Debugger {
+    /// gdb, or any gdb-compatible frontend (`gdb-multiarch`, `<triple>-gdb`, ...)
+    Gdb(String),
+    /// lldb
+    Lldb(String),
+    /// `rr record`/`rr replay`, for deterministic reverse debugging
+    Rr(String),
+    /// Windows `cdb`/`windbg`
+    Cdb(String),
+}
+
+/// All backend names recognised by `parse`, for error messages.
+pub const SUPPORTED: &[&str] = &["gdb", "lldb", "rr", "cdb", "windbg"];
+
+/// How the binary should be brought under the debugger.
+pub enum Mode<'a> {
+    /// Launch (and run) the binary fresh.
+    Run,
+    /// Attach to an already-running process.
+    Attach(u32),
+    /// Open a core dump for post-mortem analysis.
+    Core(&'a str),
+}
+
+impl Debugger {
+    /// Resolve a `--debugger` name to a backend. Accepts suffixed names
+    /// (`gdb-multiarch`, `aarch64-linux-gnu-gdb`, ...) the same way the
+    /// program itself may be renamed/prefixed on disk.
+    pub fn parse(name: &str) -> Option<Debugger> {
+        if name == "rr" {
+            Some(Debugger::Rr(name.to_string()))
+        } else if name.ends_with("gdb") || name.ends_with("gdb-multiarch") {
+            Some(Debugger::Gdb(name.to_string()))
+        } else if name.ends_with("lldb") {
+            Some(Debugger::Lldb(name.to_string()))
+        } else if name.ends_with("cdb") || name.ends_with("windbg") {
+            Some(Debugger::Cdb(name.to_string()))
+        } else {
+            None
+        }
+    }
+
+    pub fn program(&self) -> &str {
+        match self {
+            Debugger::Gdb(p) | Debugger::Lldb(p) | Debugger::Rr(p) | Debugger::Cdb(p) => p,
+        }
+    }
+
+    /// Whether this backend's argv ends in `lldb`-style flags.
+    fn is_lldb(&self) -> bool {
+        matches!(self, Debugger::Lldb(_))
+    }
+
+    /// Build the command(s) needed to debug `bin` directly on the host.
+    /// Most backends need only one; `rr` records then replays. Errs if the
+    /// backend can't support the requested `mode` (e.g. `rr` has no concept
+    /// of attaching to a running process or opening a core dump).
+    pub fn local_commands(
+        &self,
+        bin: &str,
+        child_args: &[String],
+        command_file: Option<&str>,
+        mode: &Mode,
+    ) -> Result<Vec<(String, Vec<String>)>, String> {
+        match self {
+            Debugger::Gdb(program) => {
+                let mut args = vec![];
+                if !child_args.is_empty() && matches!(mode, Mode::Run) {
+                    args.push("--args".to_string());
+                }
+                if let Some(file) = command_file {
+                    args.push("--command".to_string());
+                    args.push(file.to_string());
+                }
+                args.push(bin.to_string());
+                match mode {
+                    Mode::Run => args.extend(child_args.iter().cloned()),
+                    Mode::Attach(pid) => args.push(pid.to_string()),
+                    Mode::Core(core) => args.push(core.to_string()),
+                }
+                Ok(vec![(program.clone(), args)])
+            }
+            Debugger::Lldb(program) => {
+                let mut args = vec!["--file".to_string(), bin.to_string()];
+                match mode {
+                    Mode::Run => (),
+                    Mode::Attach(pid) => {
+                        args.push("-p".to_string());
+                        args.push(pid.to_string());
+                    }
+                    Mode::Core(core) => {
+                        args.push("-c".to_string());
+                        args.push(core.to_string());
+                    }
+                }
+                if let Some(file) = command_file {
+                    args.push("--source".to_string());
+                    args.push(file.to_string());
+                }
+                if matches!(mode, Mode::Run) && !child_args.is_empty() {
+                    args.push("--".to_string());
+                    args.extend(child_args.iter().cloned());
+                }
+                Ok(vec![(program.clone(), args)])
+            }
+            Debugger::Rr(program) => {
+                match mode {
+                    Mode::Attach(_) => {
+                        return Err(
+                            "rr has no notion of attaching to an already-running process; run it under `rr record` from the start instead".to_string(),
+                        )
+                    }
+                    Mode::Core(_) => {
+                        return Err(
+                            "rr has no notion of opening a core dump; use `rr record`/`rr replay` on a recorded trace instead".to_string(),
+                        )
+                    }
+                    Mode::Run => (),
+                }
+
+                let mut record_args = vec!["record".to_string(), bin.to_string()];
+                record_args.extend(child_args.iter().cloned());
+
+                let mut replay_args = vec!["replay".to_string()];
+                if let Some(file) = command_file {
+                    replay_args.push("-x".to_string());
+                    replay_args.push(file.to_string());
+                }
+
+                Ok(vec![(program.clone(), record_args), (program.clone(), replay_args)])
+            }
+            Debugger::Cdb(program) => {
+                let mut args = vec![];
+                if let Some(file) = command_file {
+                    let contents = fs::read_to_string(file)
+                        .unwrap_or_else(|e| panic!("failed to read command file {}: {}", file, e));
+                    let commands: Vec<_> = contents.lines().filter(|l| !l.is_empty()).collect();
+                    if !commands.is_empty() {
+                        args.push("-c".to_string());
+                        args.push(commands.join("; "));
+                    }
+                }
+                match mode {
+                    Mode::Run => {
+                        args.push(bin.to_string());
+                        args.extend(child_args.iter().cloned());
+                    }
+                    Mode::Attach(pid) => {
+                        args.push("-p".to_string());
+                        args.push(pid.to_string());
+                    }
+                    Mode::Core(core) => {
+                        args.push("-z".to_string());
+                        args.push(core.to_string());
+                    }
+                }
+                Ok(vec![(program.clone(), args)])
+            }
+        }
+    }
+
+    /// Build the command needed to attach to a remote `gdbserver` session
+    /// whose `target remote`/`file` directives already live in `command_file`.
+    pub fn remote_commands(&self, command_file: &str) -> Vec<(String, Vec<String>)> {
+        let flag = if self.is_lldb() { "--source" } else { "--command" };
+        vec![(self.program().to_string(), vec![flag.to_string(), command_file.to_string()])]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_recognises_suffixed_names() {
+        assert!(matches!(Debugger::parse("gdb"), Some(Debugger::Gdb(_))));
+        assert!(matches!(Debugger::parse("aarch64-linux-gnu-gdb"), Some(Debugger::Gdb(_))));
+        assert!(matches!(Debugger::parse("lldb"), Some(Debugger::Lldb(_))));
+        assert!(matches!(Debugger::parse("rr"), Some(Debugger::Rr(_))));
+        assert!(matches!(Debugger::parse("cdb"), Some(Debugger::Cdb(_))));
+        assert!(matches!(Debugger::parse("windbg"), Some(Debugger::Cdb(_))));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_names() {
+        assert!(Debugger::parse("notadebugger").is_none());
+    }
+
+    #[test]
+    fn gdb_run_args_shape() {
+        let gdb = Debugger::parse("gdb").unwrap();
+        let cmds = gdb.local_commands("bin", &["a".to_string()], None, &Mode::Run).unwrap();
+        assert_eq!(cmds, vec![("gdb".to_string(), vec!["--args".to_string(), "bin".to_string(), "a".to_string()])]);
+    }
+
+    #[test]
+    fn gdb_attach_args_shape() {
+        let gdb = Debugger::parse("gdb").unwrap();
+        let cmds = gdb.local_commands("bin", &[], None, &Mode::Attach(1234)).unwrap();
+        assert_eq!(cmds, vec![("gdb".to_string(), vec!["bin".to_string(), "1234".to_string()])]);
+    }
+
+    #[test]
+    fn rr_rejects_attach_and_core() {
+        let rr = Debugger::parse("rr").unwrap();
+        assert!(rr.local_commands("bin", &[], None, &Mode::Attach(1234)).is_err());
+        assert!(rr.local_commands("bin", &[], None, &Mode::Core("core")).is_err());
+    }
+
+    #[test]
+    fn cdb_attach_uses_dash_p() {
+        let cdb = Debugger::parse("cdb").unwrap();
+        let cmds = cdb.local_commands("bin", &[], None, &Mode::Attach(1234)).unwrap();
+        assert_eq!(cmds, vec![("cdb".to_string(), vec!["-p".to_string(), "1234".to_string()])]);
+    }
+
+    #[test]
+    fn cdb_core_uses_dash_z() {
+        let cdb = Debugger::parse("cdb").unwrap();
+        let cmds = cdb.local_commands("bin", &[], None, &Mode::Core("dump.dmp")).unwrap();
+        assert_eq!(cmds, vec![("cdb".to_string(), vec!["-z".to_string(), "dump.dmp".to_string()])]);
+    }
+}
@@ -16,7 +16,11 @@ use simplelog::{TermLogger, LevelFilter};
 
 use cargo_metadata::{Message};
 
-use cargo_manifest::{Manifest};
+mod breakpoint;
+mod config;
+mod debugger;
+mod remote;
+mod select;
 
 
 #[derive(StructOpt)]
@@ -27,22 +31,81 @@ struct Options {
     /// Subcommand to invoke within cargo
     subcommand: String,
 
-    #[structopt(long = "debugger", default_value = "gdb")]
-    /// Debugger to launch as a subprocess
-    debugger: String,
+    #[structopt(long = "debugger")]
+    /// Debugger to launch as a subprocess (defaults to `[package.metadata.debug]`'s
+    /// `debugger`, or "gdb" if that's unset too)
+    debugger: Option<String>,
 
     #[structopt(long = "command-file")]
     /// Command file to be passed to debugger
     command_file: Option<String>,
 
     #[structopt(long = "filter")]
-    /// Filter to match against multiple output files
+    /// Filter to match against multiple output files by filename prefix
+    /// (prefer --bin/--example/--test/--bench, which match on target kind)
     filter: Option<String>,
 
+    #[structopt(long = "bin")]
+    /// Debug the binary with this name
+    bin: Option<String>,
+
+    #[structopt(long = "example")]
+    /// Debug the example with this name
+    example: Option<String>,
+
+    #[structopt(long = "test")]
+    /// Debug the integration test binary with this name
+    test: Option<String>,
+
+    #[structopt(long = "bench")]
+    /// Debug the benchmark binary with this name
+    bench: Option<String>,
+
+    #[structopt(long = "test-name")]
+    /// Only debug the test matching this name exactly (forwarded to the test
+    /// binary as `<name> --exact`)
+    test_name: Option<String>,
+
+    #[structopt(long = "break-main")]
+    /// Stop at `main` before running, by synthesizing a temporary command file
+    break_main: bool,
+
+    #[structopt(long = "break")]
+    /// Stop at this symbol before running, by synthesizing a temporary command file
+    break_symbol: Option<String>,
+
+    #[structopt(long = "pid", conflicts_with = "core")]
+    /// Attach to an already-running process instead of launching the binary
+    pid: Option<u32>,
+
+    #[structopt(long = "core", conflicts_with = "pid")]
+    /// Open this core dump for post-mortem analysis instead of launching the binary
+    core: Option<String>,
+
     #[structopt(long = "no-run")]
     /// Print the debug command to the terminal and exit without running
     no_run: bool,
 
+    #[structopt(long = "remote")]
+    /// Debug via a runner + gdbserver instead of launching the binary directly,
+    /// regardless of whether a cross target was detected
+    remote: bool,
+
+    #[structopt(long = "runner")]
+    /// Runner used to execute the artifact under gdbserver when cross-debugging
+    /// (e.g. `qemu-aarch64 -L /usr/aarch64-linux-gnu`, or a docker wrapper)
+    runner: Option<String>,
+
+    #[structopt(long = "target-debugger")]
+    /// Host-side debugger used to attach to a remote gdbserver session
+    /// (defaults to `<triple>-gdb` if it is on `PATH`, else `gdb-multiarch`;
+    /// `lldb` for lldb sessions)
+    target_debugger: Option<String>,
+
+    #[structopt(long = "port", default_value = "2345")]
+    /// Port gdbserver listens on for remote debugging
+    port: u16,
+
     #[structopt(long = "log-level", default_value = "info")]
     /// Enable verbose logging
     level: LevelFilter,
@@ -73,7 +136,7 @@ fn main() {
         Some(o) => Some(o.iter().map(|v| v.to_str().unwrap().to_string() ).collect()),
         None => None,
     };
-    let child_opts: Option<Vec<_>> = match s.next() {
+    let mut child_opts: Option<Vec<_>> = match s.next() {
         Some(o) => Some(o.iter().map(|v| v.to_str().unwrap().to_string() ).collect()),
         None => None,
     };
@@ -81,6 +144,13 @@ fn main() {
     // Load options
     let o = Options::from_iter(&config_opts);
 
+    // Forward --test-name as an exact-match filter to the test binary
+    if let Some(name) = &o.test_name {
+        let opts = child_opts.get_or_insert_with(Vec::new);
+        opts.push(name.clone());
+        opts.push("--exact".to_string());
+    }
+
     // Setup logging
     TermLogger::init(o.level, simplelog::Config::default()).unwrap();
 
@@ -91,11 +161,12 @@ fn main() {
 
     trace!("loading package file");
 
-    let toml: Manifest = Manifest::from_path("Cargo.toml").expect("Failed to read Cargo.toml");
-
-    let package = toml.package.expect("No package available").name;
+    let manifest = config::load("Cargo.toml");
+    let package = manifest.package_name;
+    let debug_meta = manifest.debug;
 
     trace!("found package: '{}'", package);
+    trace!("[package.metadata.debug]: {:?}", debug_meta);
 
     trace!("building cargo command");
 
@@ -112,7 +183,7 @@ fn main() {
     }
 
     // Attach additional arguments
-    if let Some(opts) = cargo_opts {
+    if let Some(opts) = &cargo_opts {
         cargo_cmd.args(opts);
     }
 
@@ -136,88 +207,142 @@ fn main() {
     handle.wait().expect("cargo command failed, try running the command directly");
     trace!("command executed");
 
-    // Find the output(s) we care about
-    let outputs: Vec<_> = artifacts.iter().filter_map(|a| {      
-        if let Some(x) = &a.executable {
-            return Some(x.clone())
-        }
-
-        None
-    } ).collect();
+    // Determine the kind-based selector, if one was given
+    let selector = o
+        .bin
+        .as_ref()
+        .map(|name| select::Selector { kind: "bin", name })
+        .or_else(|| o.example.as_ref().map(|name| select::Selector { kind: "example", name }))
+        .or_else(|| o.test.as_ref().map(|name| select::Selector { kind: "test", name }))
+        .or_else(|| o.bench.as_ref().map(|name| select::Selector { kind: "bench", name }));
+
+    // Find the output(s) we care about, falling back to the manifest's
+    // default filter if neither a selector nor --filter were given
+    let filter = o.filter.clone().or_else(|| debug_meta.filter.clone());
+    let outputs = select::select(&artifacts, &filter, &selector);
     trace!("found {} outputs: {:?}", outputs.len(), outputs);
 
-    // Filter / select outputs
-    let bin = match o.filter {
-        Some(f) => {
-            outputs.iter().find(|p| {
-                let file_name = p.file_name().unwrap().to_str().unwrap();
-                file_name.starts_with(&f)
-            } ).expect("no fi")
-        },
-        None => {
-            if outputs.len() > 1 {
-                error!("found multiple output arguments, pass --filter=X argument to select a specific output");
-                let names: Vec<_> = outputs.iter().filter_map(|o| o.file_name() ).collect();
-                error!("{:#?}", names);
+    let bin = if outputs.len() > 1 {
+        error!("found multiple output artifacts, pass --bin/--example/--test/--bench (or --filter) to select a specific one");
+        select::print_available(&artifacts);
+        return
+    } else {
+        match outputs.get(0) {
+            Some(bin) => *bin,
+            None => {
+                error!("no matching output artifacts found");
+                select::print_available(&artifacts);
                 return
             }
-
-            outputs.get(0).expect("no viable output artifacts found")
         }
     };
 
     info!("selected binary: {:?}", bin);
 
-    let debugger = o.debugger;
+    // Detect cross-compilation: a requested `--target`/`CARGO_BUILD_TARGET`
+    // that differs from the host means the artifact can't run locally.
+    let target_triple = remote::requested_target(&cargo_opts);
+    let host_triple = remote::host_triple();
+    let is_remote = o.remote
+        || match (&target_triple, &host_triple) {
+            (Some(target), Some(host)) => target != host,
+            _ => false,
+        };
+
+    // A symbol to break on before running, from --break-main or --break
+    let break_symbol = o.break_symbol.clone().or_else(|| {
+        if o.break_main {
+            Some("main".to_string())
+        } else {
+            None
+        }
+    });
 
-    let mut debug_args: Vec<String> = vec![];
+    // CLI flags override [package.metadata.debug], which overrides the built-in default
+    let debugger_name = debug_meta.resolve_debugger(&o.debugger);
+    let manifest_command_file = debug_meta.resolve_command_file(&o.command_file);
 
-    if debugger.ends_with("gdb") {
-        // Prepare GDB to accept child options
-        if let Some(_opts) = &child_opts {
-            debug_args.push("--args".to_string());
-        }
+    let mut runner_child = None;
 
-        // Append command file if provided
-        if let Some(command_file) = o.command_file {
-            debug_args.push("--command".to_string());
-            debug_args.push(command_file);
-        }
+    let bin_str = bin.clone().to_str().unwrap().to_string();
+    let child_args = child_opts.clone().unwrap_or_default();
 
-        // Specify file to be debugged
-        debug_args.push(bin.clone().to_str().unwrap().to_string());
+    // Resolve which backend actually runs the session: for remote sessions
+    // this may differ from the local `--debugger` (e.g. `gdb` -> `gdb-multiarch`).
+    let resolved_debugger_name = if is_remote {
+        remote::target_debugger(&o.target_debugger, &debugger_name, target_triple.as_deref().unwrap_or("unknown"))
+    } else {
+        debugger_name
+    };
 
-        // Append child options
-        if let Some(opts) = &child_opts {
-            debug_args.append(&mut opts.clone());
-        }
-    } else if debugger.ends_with("lldb") {
-        // Specify file to be debugged
-        debug_args.push("--file".to_string());
-        debug_args.push(bin.clone().to_str().unwrap().to_string());
-
-        // Append command file if provided
-        if let Some(command_file) = o.command_file {
-            debug_args.push("--source".to_string());
-            debug_args.push(command_file);
+    let backend = match debugger::Debugger::parse(&resolved_debugger_name) {
+        Some(b) => b,
+        None => {
+            error!("unsupported or unrecognised debugger '{}', supported backends: {}", resolved_debugger_name, debugger::SUPPORTED.join(", "));
+            return;
         }
+    };
 
-        // Append child options
-        if let Some(opts) = child_opts {
-            debug_args.push("--".to_string());
-            debug_args.append(&mut opts.clone());
+    let commands = if is_remote {
+        trace!("cross-debugging detected (target: {:?}, host: {:?})", target_triple, host_triple);
+
+        let runner = o.runner.expect("--runner is required for remote/cross debugging, e.g. `--runner qemu-aarch64`");
+
+        let (child, port) = remote::start_runner(&runner, o.port, &bin_str, &child_args)
+            .expect("failed to start runner");
+        info!("runner listening on port {}", port);
+        runner_child = Some(child);
+
+        let command_file_path = std::env::temp_dir().join(format!("cargo-debug-remote-{}.gdb", std::process::id()));
+        let mut contents = remote::remote_command_file_contents(backend.program(), &bin_str, "localhost", port);
+        if let Some(symbol) = &break_symbol {
+            // `target remote` has already attached the process; gdb rejects
+            // `run` against a remote target ("The "remote" target does not
+            // support "run""), so resume with `continue` instead.
+            contents.push_str(&breakpoint::command_file_contents(
+                backend.program(),
+                symbol,
+                breakpoint::Launch::Continue,
+            ));
         }
+        std::fs::write(&command_file_path, contents).expect("failed to write remote command file");
+
+        backend.remote_commands(command_file_path.to_str().unwrap())
     } else {
-        error!("unsupported or unrecognised debugger {}", debugger);
-        return;
-    }
+        // `--pid` attaches to a process that is already running; post-mortem
+        // mode never runs one either. Only a fresh launch needs `run`.
+        let mode = if let Some(pid) = o.pid {
+            debugger::Mode::Attach(pid)
+        } else if let Some(core) = &o.core {
+            debugger::Mode::Core(core)
+        } else {
+            debugger::Mode::Run
+        };
+        let launch = match mode {
+            debugger::Mode::Run => breakpoint::Launch::Run,
+            debugger::Mode::Attach(_) => breakpoint::Launch::Continue,
+            debugger::Mode::Core(_) => breakpoint::Launch::None,
+        };
+
+        // Resolve the command file to use, synthesizing one with the requested
+        // breakpoint if --break-main/--break was given.
+        let command_file =
+            breakpoint::resolve_command_file(backend.program(), &manifest_command_file, &break_symbol, launch);
+
+        backend.local_commands(&bin_str, &child_args, command_file.as_deref(), &mode).unwrap_or_else(|e| {
+            error!("{}", e);
+            std::process::exit(1);
+        })
+    };
 
-    trace!("synthesized debug arguments: {:?}", debug_args);
+    trace!("synthesized debug commands: {:?}", commands);
 
     if o.no_run {
         trace!("no-run selected, exiting");
         println!("Debug command: ");
-        println!("{} {}", &debugger, debug_args.join(" "));
+        for (program, args) in &commands {
+            println!("{} {}", program, args.join(" "));
+        }
         std::process::exit(0);
     }
 
@@ -236,15 +361,21 @@ fn main() {
         }
     }).expect("Error setting Ctrl-C handler");
 
+    for (program, args) in commands {
+        let mut debug_cmd = Command::new(&program);
+        debug_cmd.args(args);
 
-    let mut debug_cmd = Command::new(&debugger);
-    debug_cmd.args(debug_args);
+        trace!("synthesized debug command: {:?}", debug_cmd);
 
-    trace!("synthesized debug command: {:?}", debug_cmd);
-    
-    debug_cmd.status().expect("error running debug command");
+        debug_cmd.status().expect("error running debug command");
+    }
 
     trace!("debug command done");
+
+    if let Some(mut child) = runner_child {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
 }
 
 
@@ -0,0 +1,227 @@
+//! Helpers for cross-compilation-aware remote debugging via a runner +
+//! `gdbserver`.
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+
+/// Find a `--target <triple>` (or `--target=<triple>`) passthrough argument,
+/// falling back to `CARGO_BUILD_TARGET`, mirroring how cargo itself resolves
+/// a requested `CompileTarget`.
+pub fn requested_target(cargo_opts: &Option<Vec<String>>) -> Option<String> {
+    if let Some(opts) = cargo_opts {
+        let mut iter = opts.iter();
+        while let Some(opt) = iter.next() {
+            if opt == "--target" {
+                if let Some(triple) = iter.next() {
+                    return Some(triple.clone());
+                }
+            } else if let Some(triple) = opt.strip_prefix("--target=") {
+                return Some(triple.to_string());
+            }
+        }
+    }
+
+    std::env::var("CARGO_BUILD_TARGET").ok()
+}
+
+/// Query the host triple from `rustc -vV`, e.g. `x86_64-unknown-linux-gnu`.
+pub fn host_triple() -> Option<String> {
+    let output = Command::new("rustc").arg("-vV").output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(|triple| triple.to_string())
+}
+
+/// Spawn the user-supplied runner wrapping `gdbserver :PORT <bin> <child args>`,
+/// then scrape the "Listening on port N" line gdbserver prints on stderr so we
+/// can hand the real (possibly kernel-assigned) port to the host debugger.
+pub fn start_runner(
+    runner: &str,
+    port: u16,
+    bin: &str,
+    child_args: &[String],
+) -> std::io::Result<(Child, u16)> {
+    let mut parts = runner.split_whitespace();
+    let program = parts.next().expect("--runner must not be empty");
+
+    let mut cmd = Command::new(program);
+    cmd.args(parts);
+    cmd.arg("gdbserver");
+    cmd.arg(format!(":{}", port));
+    cmd.arg(bin);
+    cmd.args(child_args);
+    cmd.stderr(Stdio::piped());
+
+    trace!("synthesized runner command: {:?}", cmd);
+
+    let mut child = cmd.spawn()?;
+
+    let stderr = child.stderr.take().expect("runner stderr not piped");
+    let mut reader = BufReader::new(stderr);
+    let mut listening_port = port;
+    let mut line = String::new();
+    while reader.read_line(&mut line)? != 0 {
+        trace!("runner: {}", line.trim_end());
+        if let Some(idx) = line.find("Listening on port ") {
+            let rest = &line[idx + "Listening on port ".len()..];
+            if let Some(parsed) = rest.trim().split(|c: char| !c.is_ascii_digit()).next() {
+                if let Ok(parsed_port) = parsed.parse() {
+                    listening_port = parsed_port;
+                }
+            }
+            break;
+        }
+        line.clear();
+    }
+
+    // Keep draining stderr for the life of the child: otherwise, once the
+    // pipe buffer fills with whatever the runner/gdbserver logs for the rest
+    // of the session, the child blocks on write and the whole session hangs.
+    thread::spawn(move || {
+        let mut line = String::new();
+        while let Ok(n) = reader.read_line(&mut line) {
+            if n == 0 {
+                break;
+            }
+            trace!("runner: {}", line.trim_end());
+            line.clear();
+        }
+    });
+
+    Ok((child, listening_port))
+}
+
+/// Build the host-side debugger's startup commands for attaching to a remote
+/// `gdbserver` session.
+pub fn remote_command_file_contents(debugger: &str, bin: &str, host: &str, port: u16) -> String {
+    if debugger.ends_with("lldb") {
+        format!(
+            "platform select remote-gdb-server\nfile {}\ngdb-remote {}:{}\n",
+            bin, host, port
+        )
+    } else {
+        format!("file {}\ntarget remote {}:{}\n", bin, host, port)
+    }
+}
+
+/// Pick the host-side debugger used to attach to a remote session: an
+/// explicit override, `lldb` for lldb sessions, or (for gdb) the
+/// triple-prefixed cross gdb if one is actually installed (it's built
+/// specifically for the target, so it's preferred when present), falling
+/// back to the generic `gdb-multiarch` package otherwise.
+pub fn target_debugger(override_debugger: &Option<String>, local_debugger: &str, triple: &str) -> String {
+    if let Some(d) = override_debugger {
+        return d.clone();
+    }
+
+    if local_debugger.ends_with("lldb") {
+        return "lldb".to_string();
+    }
+
+    let triple_gdb = format!("{}-gdb", triple);
+    let triple_gdb_exists = command_exists(&triple_gdb);
+    resolve_gdb_variant(triple_gdb, triple_gdb_exists)
+}
+
+/// Pick between the triple-prefixed cross gdb and `gdb-multiarch`, given
+/// whether the former is actually installed. Split out from
+/// `target_debugger` so the decision itself is testable without touching
+/// the filesystem or `PATH`.
+fn resolve_gdb_variant(triple_gdb: String, triple_gdb_exists: bool) -> String {
+    if triple_gdb_exists {
+        triple_gdb
+    } else {
+        "gdb-multiarch".to_string()
+    }
+}
+
+/// Whether `program` resolves to a file somewhere on `PATH`.
+fn command_exists(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| exists_in_path(program, &paths.to_string_lossy()))
+        .unwrap_or(false)
+}
+
+fn exists_in_path(program: &str, path: &str) -> bool {
+    std::env::split_paths(path).any(|dir| dir.join(program).is_file())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn requested_target_reads_separate_arg() {
+        let opts = Some(vec!["--target".to_string(), "aarch64-unknown-linux-gnu".to_string()]);
+        assert_eq!(requested_target(&opts), Some("aarch64-unknown-linux-gnu".to_string()));
+    }
+
+    #[test]
+    fn requested_target_reads_equals_form() {
+        let opts = Some(vec!["--target=aarch64-unknown-linux-gnu".to_string()]);
+        assert_eq!(requested_target(&opts), Some("aarch64-unknown-linux-gnu".to_string()));
+    }
+
+    #[test]
+    fn requested_target_is_none_without_target_or_env() {
+        std::env::remove_var("CARGO_BUILD_TARGET");
+        assert_eq!(requested_target(&Some(vec!["--release".to_string()])), None);
+    }
+
+    #[test]
+    fn remote_command_file_contents_gdb() {
+        assert_eq!(
+            remote_command_file_contents("gdb", "bin", "localhost", 1234),
+            "file bin\ntarget remote localhost:1234\n"
+        );
+    }
+
+    #[test]
+    fn remote_command_file_contents_lldb() {
+        assert_eq!(
+            remote_command_file_contents("lldb", "bin", "localhost", 1234),
+            "platform select remote-gdb-server\nfile bin\ngdb-remote localhost:1234\n"
+        );
+    }
+
+    #[test]
+    fn target_debugger_override_always_wins() {
+        let over = Some("custom-gdb".to_string());
+        assert_eq!(target_debugger(&over, "gdb", "aarch64-unknown-linux-gnu"), "custom-gdb");
+    }
+
+    #[test]
+    fn target_debugger_lldb_stays_lldb() {
+        assert_eq!(target_debugger(&None, "lldb", "aarch64-unknown-linux-gnu"), "lldb");
+    }
+
+    #[test]
+    fn resolve_gdb_variant_prefers_triple_gdb_when_installed() {
+        assert_eq!(
+            resolve_gdb_variant("aarch64-unknown-linux-gnu-gdb".to_string(), true),
+            "aarch64-unknown-linux-gnu-gdb"
+        );
+    }
+
+    #[test]
+    fn resolve_gdb_variant_falls_back_to_multiarch_when_missing() {
+        assert_eq!(resolve_gdb_variant("aarch64-unknown-linux-gnu-gdb".to_string(), false), "gdb-multiarch");
+    }
+
+    #[test]
+    fn exists_in_path_finds_installed_triple_gdb() {
+        let dir = std::env::temp_dir().join(format!("cargo-debug-test-path-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        File::create(dir.join("aarch64-unknown-linux-gnu-gdb")).unwrap();
+
+        assert!(exists_in_path("aarch64-unknown-linux-gnu-gdb", dir.to_str().unwrap()));
+        assert!(!exists_in_path("some-other-gdb", dir.to_str().unwrap()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
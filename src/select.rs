@@ -0,0 +1,128 @@
+//! Output artifact selection.
+//!
+//! `cargo build --message-format=json` can emit several executable
+//! artifacts in one run (a lib's unit tests, several `--example`s, an
+//! integration test, ...). Selecting on filename prefix alone can't tell
+//! a `--bin foo` apart from an example or test binary that happens to
+//! share a name, so we select on the artifact's `target.kind` (as cargo
+//! itself does internally) plus its name.
+
+use std::path::PathBuf;
+
+use cargo_metadata::Artifact;
+
+/// The target kinds cargo reports that can produce something debuggable.
+const KINDS: &[&str] = &["bin", "example", "test", "bench"];
+
+/// A single named selector, e.g. `--bin foo` or `--test bar`.
+pub struct Selector<'a> {
+    pub kind: &'a str,
+    pub name: &'a str,
+}
+
+/// Collect the executable artifacts matching one of the `--bin`/`--example`/
+/// `--test`/`--bench` selectors, or fall back to the legacy filename
+/// `--filter`, or (if nothing was requested) every executable artifact.
+pub fn select<'a>(
+    artifacts: &'a [Artifact],
+    filter: &Option<String>,
+    selector: &Option<Selector>,
+) -> Vec<&'a PathBuf> {
+    if let Some(s) = selector {
+        return artifacts
+            .iter()
+            .filter(|a| a.target.kind.iter().any(|k| k == s.kind) && a.target.name == s.name)
+            .filter_map(|a| a.executable.as_ref())
+            .collect();
+    }
+
+    if let Some(f) = filter {
+        return artifacts
+            .iter()
+            .filter_map(|a| a.executable.as_ref())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(f.as_str()))
+            })
+            .collect();
+    }
+
+    artifacts.iter().filter_map(|a| a.executable.as_ref()).collect()
+}
+
+/// Print every selectable artifact grouped by kind, mirroring cargo's own
+/// `print_available_binaries`/`print_available_examples` helpers, so the
+/// user knows exactly what to pass to disambiguate.
+pub fn print_available(artifacts: &[Artifact]) {
+    for kind in KINDS {
+        let names: Vec<_> = artifacts
+            .iter()
+            .filter(|a| a.target.kind.iter().any(|k| k == kind))
+            .map(|a| a.target.name.as_str())
+            .collect();
+
+        if names.is_empty() {
+            continue;
+        }
+
+        error!("available {}s:", kind);
+        for name in names {
+            error!("    {}", name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Build an `Artifact` the same way production code receives one: by
+    /// parsing a `cargo build --message-format=json` compiler-artifact line.
+    fn artifact(kind: &str, name: &str, executable: &str) -> Artifact {
+        let json = format!(
+            r#"{{"reason":"compiler-artifact","package_id":"pkg 0.1.0 (path+file:///pkg)","target":{{"name":"{name}","kind":["{kind}"],"src_path":"/pkg/src/main.rs"}},"profile":{{"opt_level":"0","debuginfo":2,"debug_assertions":true,"overflow_checks":true,"test":false}},"features":[],"filenames":["{executable}"],"executable":"{executable}","fresh":false}}"#,
+            kind = kind,
+            name = name,
+            executable = executable,
+        );
+        match cargo_metadata::parse_messages(json.as_bytes())
+            .next()
+            .expect("one message")
+            .expect("valid message")
+        {
+            cargo_metadata::Message::CompilerArtifact(a) => a,
+            _ => panic!("expected a compiler artifact message"),
+        }
+    }
+
+    #[test]
+    fn selector_matches_by_kind_and_name() {
+        let artifacts = vec![
+            artifact("bin", "foo", "/target/debug/foo"),
+            artifact("example", "foo", "/target/debug/examples/foo"),
+        ];
+        let selector = Some(Selector { kind: "bin", name: "foo" });
+
+        let found = select(&artifacts, &None, &selector);
+        assert_eq!(found, vec![&PathBuf::from("/target/debug/foo")]);
+    }
+
+    #[test]
+    fn filter_falls_back_to_filename_prefix() {
+        let artifacts = vec![artifact("bin", "foo", "/target/debug/foo-bar")];
+
+        let found = select(&artifacts, &Some("foo".to_string()), &None);
+        assert_eq!(found, vec![&PathBuf::from("/target/debug/foo-bar")]);
+    }
+
+    #[test]
+    fn no_selector_or_filter_returns_everything() {
+        let artifacts = vec![
+            artifact("bin", "foo", "/target/debug/foo"),
+            artifact("bin", "bar", "/target/debug/bar"),
+        ];
+
+        assert_eq!(select(&artifacts, &None, &None).len(), 2);
+    }
+}